@@ -0,0 +1,83 @@
+// src/config.rs
+use std::env;
+
+/// Runtime configuration loaded from the environment (and `.env` if present).
+/// Failing fast here means a missing secret or connection string surfaces as
+/// a clear startup error instead of a silent, insecure fallback.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub jwt_secret: Vec<u8>,
+    pub mongodb_uri: String,
+    pub database_name: String,
+    pub collection_name: String,
+    pub server_port: u16,
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_allowed_methods: Vec<String>,
+    pub cors_allowed_headers: Vec<String>,
+    pub refresh_collection_name: String,
+    pub access_token_ttl_minutes: i64,
+    pub refresh_token_ttl_days: i64,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        dotenvy::dotenv().ok();
+
+        Config {
+            jwt_secret: Self::require("JWT_SECRET").into_bytes(),
+            mongodb_uri: Self::require("MONGODB_URI"),
+            database_name: Self::require("DATABASE_NAME"),
+            collection_name: Self::require("COLLECTION_NAME"),
+            server_port: Self::require("SERVER_PORT")
+                .parse()
+                .expect("SERVER_PORT must be a valid port number"),
+            cors_allowed_origins: Self::origins("CORS_ALLOWED_ORIGINS", &["http://localhost:3000"]),
+            cors_allowed_methods: Self::list("CORS_ALLOWED_METHODS", &["GET", "POST", "OPTIONS", "DELETE"]),
+            cors_allowed_headers: Self::list("CORS_ALLOWED_HEADERS", &["Content-Type", "Authorization"]),
+            refresh_collection_name: Self::optional("REFRESH_COLLECTION_NAME", "refresh_tokens"),
+            access_token_ttl_minutes: Self::optional("ACCESS_TOKEN_TTL_MINUTES", "15")
+                .parse()
+                .expect("ACCESS_TOKEN_TTL_MINUTES must be a valid number"),
+            refresh_token_ttl_days: Self::optional("REFRESH_TOKEN_TTL_DAYS", "30")
+                .parse()
+                .expect("REFRESH_TOKEN_TTL_DAYS must be a valid number"),
+        }
+    }
+
+    fn require(key: &str) -> String {
+        env::var(key).unwrap_or_else(|_| panic!("missing required environment variable: {key}"))
+    }
+
+    /// Reads `key`, falling back to `default` when unset.
+    fn optional(key: &str, default: &str) -> String {
+        env::var(key).unwrap_or_else(|_| default.to_string())
+    }
+
+    /// Reads a comma-separated list from `key`, falling back to `default` when unset.
+    /// Blank entries (e.g. a trailing comma or an explicitly empty value) are dropped.
+    fn list(key: &str, default: &[&str]) -> Vec<String> {
+        match env::var(key) {
+            Ok(value) => value
+                .split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect(),
+            Err(_) => default.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    /// Like `list`, but additionally fails fast if an entry has no `://`
+    /// separator or nothing after it, since warp's CORS builder panics on
+    /// malformed origins rather than returning an error. The scheme and host
+    /// themselves are not validated here.
+    fn origins(key: &str, default: &[&str]) -> Vec<String> {
+        let origins = Self::list(key, default);
+        for origin in &origins {
+            match origin.split_once("://") {
+                Some((_, host)) if !host.is_empty() => {}
+                _ => panic!("invalid entry in {key}: \"{origin}\" is not a valid scheme://host origin"),
+            }
+        }
+        origins
+    }
+}
@@ -1,14 +1,47 @@
 // src/database.rs
-use mongodb::{Client, Collection};
-use crate::models::User;
+use mongodb::bson::doc;
+use mongodb::options::IndexOptions;
+use mongodb::{Client, Collection, Database, IndexModel};
+use crate::models::{RefreshToken, User};
 
-const DATABASE_NAME: &str = "rust_server";
-const COLLECTION_NAME: &str = "users";
-
-pub async fn get_collection() -> Collection<User> {
-    let client = Client::with_uri_str("mongodb://localhost:27017")
+pub async fn connect(uri: &str, database_name: &str) -> Database {
+    let client = Client::with_uri_str(uri)
         .await
         .expect("Failed to connect to MongoDB");
-    let database = client.database(DATABASE_NAME);
-    database.collection(COLLECTION_NAME)
+    client.database(database_name)
+}
+
+pub async fn get_collection(database: &Database, collection_name: &str) -> Collection<User> {
+    let collection: Collection<User> = database.collection(collection_name);
+
+    // Enforce one account per email at the database level so a duplicate
+    // registration fails with a Mongo 11000 error instead of silently
+    // inserting a second record.
+    let email_index = IndexModel::builder()
+        .keys(doc! { "email": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    collection
+        .create_index(email_index, None)
+        .await
+        .expect("Failed to create unique index on users.email");
+
+    collection
+}
+
+pub async fn get_refresh_token_collection(database: &Database, collection_name: &str) -> Collection<RefreshToken> {
+    let collection: Collection<RefreshToken> = database.collection(collection_name);
+
+    // `jti` is the lookup key presented in every refresh request, so it must
+    // be unique and indexed.
+    let jti_index = IndexModel::builder()
+        .keys(doc! { "jti": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    collection
+        .create_index(jti_index, None)
+        .await
+        .expect("Failed to create unique index on refresh_tokens.jti");
+
+    collection
 }
\ No newline at end of file
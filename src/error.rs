@@ -0,0 +1,39 @@
+// src/error.rs
+use serde::Serialize;
+use std::convert::Infallible;
+use warp::http::StatusCode;
+use warp::{Rejection, Reply};
+
+/// Application-level failures that can be raised as a warp rejection and
+/// mapped to a JSON error response by `handle_rejection`.
+#[derive(Debug)]
+pub enum ApiError {
+    Unauthorized,
+    Forbidden,
+    UserAlreadyExists,
+    Internal,
+}
+
+impl warp::reject::Reject for ApiError {}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
+    let (code, message) = if err.is_not_found() {
+        (StatusCode::NOT_FOUND, "not found".to_string())
+    } else if let Some(e) = err.find::<ApiError>() {
+        match e {
+            ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, "unauthorized".to_string()),
+            ApiError::Forbidden => (StatusCode::FORBIDDEN, "forbidden".to_string()),
+            ApiError::UserAlreadyExists => (StatusCode::CONFLICT, "user already exists".to_string()),
+            ApiError::Internal => (StatusCode::INTERNAL_SERVER_ERROR, "internal server error".to_string()),
+        }
+    } else {
+        (StatusCode::INTERNAL_SERVER_ERROR, "internal server error".to_string())
+    };
+
+    Ok(warp::reply::with_status(warp::reply::json(&ErrorResponse { message }), code))
+}
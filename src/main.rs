@@ -1,78 +1,446 @@
 // src/main.rs
+mod config;
 mod database;
+mod error;
 mod models;
 
-use warp::Filter;
+use config::Config;
+use error::ApiError;
+
+use warp::{Filter, Rejection};
 use serde::{Deserialize, Serialize};
-use jsonwebtoken::{encode, Header, EncodingKey};
-use bcrypt::{hash, DEFAULT_COST};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use bcrypt::{hash, verify, DEFAULT_COST};
 use chrono::{Utc, Duration};
+use mongodb::bson::doc;
+use mongodb::error::{ErrorKind, WriteFailure};
 use mongodb::Collection;
 use std::convert::Infallible;
+use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Claims {
     email: String,
+    role: String,
     exp: usize,
 }
 
+// Default role granted to newly registered accounts
+const DEFAULT_ROLE: &str = "user";
+
 #[derive(Debug, Deserialize)]
 struct RegisterRequest {
     email: String,
     password: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct LoginRequest {
+    email: String,
+    password: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LogoutRequest {
+    refresh_token: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+}
+
+#[derive(Debug, Serialize)]
+struct WhoamiResponse {
+    email: String,
+}
+
 #[derive(Debug, Serialize)]
-struct RegisterResponse {
-    token: String,
+struct StatusResponse {
+    status: &'static str,
+}
+
+/// Shared handles and settings every route needs, cloned cheaply into each
+/// filter chain (the Mongo collections and secret are themselves `Arc`-backed
+/// or small enough to clone directly).
+#[derive(Clone)]
+struct AppState {
+    users: Collection<models::User>,
+    refresh_tokens: Collection<models::RefreshToken>,
+    jwt_secret: Vec<u8>,
+    access_token_ttl_minutes: i64,
+    refresh_token_ttl_days: i64,
 }
 
-// JWT secret key (replace with a secure key in production)
-const JWT_SECRET: &[u8] = b"your_secret_key";
+fn with_state(state: AppState) -> impl Filter<Extract = (AppState,), Error = Infallible> + Clone {
+    warp::any().map(move || state.clone())
+}
+
+// Mint a short-lived access token carrying the user's email and role
+fn create_access_token(email: &str, role: &str, state: &AppState) -> Result<String, Rejection> {
+    let expiration = Utc::now() + Duration::minutes(state.access_token_ttl_minutes);
+    let claims = Claims {
+        email: email.to_string(),
+        role: role.to_string(),
+        exp: expiration.timestamp() as usize,
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(&state.jwt_secret))
+        .map_err(|_| warp::reject::custom(ApiError::Internal))
+}
+
+// Mint a long-lived refresh token. Only a hash of its secret half is
+// persisted, keyed by the public `jti`, so a leaked database can't be
+// replayed as a valid token.
+async fn create_refresh_token(email: &str, state: &AppState) -> Result<String, Rejection> {
+    let jti = Uuid::new_v4().to_string();
+    let secret = Uuid::new_v4().to_string();
+    let secret_hash = hash(&secret, DEFAULT_COST).map_err(|_| warp::reject::custom(ApiError::Internal))?;
+    let expires_at = (Utc::now() + Duration::days(state.refresh_token_ttl_days)).timestamp();
+
+    let refresh_token = models::RefreshToken {
+        jti: jti.clone(),
+        email: email.to_string(),
+        secret_hash,
+        expires_at,
+        revoked: false,
+    };
+    state
+        .refresh_tokens
+        .insert_one(refresh_token, None)
+        .await
+        .map_err(|_| warp::reject::custom(ApiError::Internal))?;
+
+    Ok(format!("{jti}.{secret}"))
+}
 
 // Register a new user
-async fn register_user(
-    user: RegisterRequest,
-    collection: Collection<models::User>,
-) -> Result<impl warp::Reply, Infallible> {
+async fn register_user(user: RegisterRequest, state: AppState) -> Result<impl warp::Reply, Rejection> {
     // Hash the password
-    let hashed_password = hash(&user.password, DEFAULT_COST).unwrap();
+    let hashed_password = hash(&user.password, DEFAULT_COST).map_err(|_| warp::reject::custom(ApiError::Internal))?;
 
     // Create a new user
     let new_user = models::User {
         email: user.email.clone(),
         password: hashed_password,
+        role: DEFAULT_ROLE.to_string(),
     };
 
     // Insert the user into MongoDB
-    collection.insert_one(new_user, None).await.unwrap();
+    if let Err(e) = state.users.insert_one(new_user, None).await {
+        if let ErrorKind::Write(WriteFailure::WriteError(we)) = &*e.kind {
+            if we.code == 11000 {
+                return Err(warp::reject::custom(ApiError::UserAlreadyExists));
+            }
+        }
+        return Err(warp::reject::custom(ApiError::Internal));
+    }
 
-    // Generate a JWT
-    let expiration = Utc::now() + Duration::hours(24);
-    let claims = Claims {
-        email: user.email,
-        exp: expiration.timestamp() as usize,
+    let access_token = create_access_token(&user.email, DEFAULT_ROLE, &state)?;
+    let refresh_token = create_refresh_token(&user.email, &state).await?;
+
+    Ok(warp::reply::json(&TokenResponse { access_token, refresh_token }))
+}
+
+// Authenticate an existing user and mint a fresh token pair
+async fn login_user(login: LoginRequest, state: AppState) -> Result<impl warp::Reply, Rejection> {
+    let user = state
+        .users
+        .find_one(doc! { "email": &login.email }, None)
+        .await
+        .map_err(|_| warp::reject::custom(ApiError::Internal))?;
+
+    let user = match user {
+        Some(user) => user,
+        None => return Err(warp::reject::custom(ApiError::Unauthorized)),
     };
-    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(JWT_SECRET)).unwrap();
 
-    // Return the token
-    Ok(warp::reply::json(&RegisterResponse { token }))
+    match verify(&login.password, &user.password) {
+        Ok(true) => {
+            let access_token = create_access_token(&user.email, &user.role, &state)?;
+            let refresh_token = create_refresh_token(&user.email, &state).await?;
+            Ok(warp::reply::json(&TokenResponse { access_token, refresh_token }))
+        }
+        _ => Err(warp::reject::custom(ApiError::Unauthorized)),
+    }
+}
+
+// Looks up the still-valid refresh token matching the secret half of
+// `presented`, rejecting with `ApiError::Unauthorized` if it is missing,
+// revoked, expired, or the secret doesn't match its stored hash.
+async fn find_valid_refresh_token(
+    presented: &str,
+    state: &AppState,
+) -> Result<models::RefreshToken, Rejection> {
+    let (jti, secret) = presented
+        .split_once('.')
+        .ok_or_else(|| warp::reject::custom(ApiError::Unauthorized))?;
+
+    let stored = state
+        .refresh_tokens
+        .find_one(doc! { "jti": jti }, None)
+        .await
+        .map_err(|_| warp::reject::custom(ApiError::Internal))?
+        .ok_or_else(|| warp::reject::custom(ApiError::Unauthorized))?;
+
+    if stored.revoked || stored.expires_at < Utc::now().timestamp() {
+        return Err(warp::reject::custom(ApiError::Unauthorized));
+    }
+    if !verify(secret, &stored.secret_hash).unwrap_or(false) {
+        return Err(warp::reject::custom(ApiError::Unauthorized));
+    }
+
+    Ok(stored)
+}
+
+// True if `stored` is usable by the holder of `secret` right now: not
+// revoked, not expired, and its hash matches the presented secret. Split out
+// as a pure function so the rotation/reuse logic below is unit-testable
+// without a live MongoDB connection.
+fn is_refresh_token_usable(stored: &models::RefreshToken, secret: &str, now: i64) -> bool {
+    !stored.revoked && stored.expires_at > now && verify(secret, &stored.secret_hash).unwrap_or(false)
+}
+
+// Validate a presented refresh token, rotate it, and return a fresh access
+// token plus a replacement refresh token.
+//
+// The secret is bcrypt-verified against the stored hash *before* anything is
+// mutated, so an attacker who only has the (plaintext, leaked-database-visible)
+// `jti` but not the secret can never burn a live token by guessing. The
+// revocation itself still happens via a conditional `find_one_and_update`
+// pinned to the exact `secret_hash` just verified and `revoked: false`, so a
+// concurrent rotation of the same token loses the race and is rejected as
+// reuse rather than both succeeding.
+async fn refresh(req: RefreshRequest, state: AppState) -> Result<impl warp::Reply, Rejection> {
+    let (jti, secret) = req
+        .refresh_token
+        .split_once('.')
+        .ok_or_else(|| warp::reject::custom(ApiError::Unauthorized))?;
+
+    let stored = state
+        .refresh_tokens
+        .find_one(doc! { "jti": jti }, None)
+        .await
+        .map_err(|_| warp::reject::custom(ApiError::Internal))?
+        .ok_or_else(|| warp::reject::custom(ApiError::Unauthorized))?;
+
+    let now = Utc::now().timestamp();
+    if !is_refresh_token_usable(&stored, secret, now) {
+        return Err(warp::reject::custom(ApiError::Unauthorized));
+    }
+
+    let filter = doc! {
+        "jti": jti,
+        "revoked": false,
+        "secret_hash": &stored.secret_hash,
+    };
+    let update = doc! { "$set": { "revoked": true } };
+    state
+        .refresh_tokens
+        .find_one_and_update(filter, update, None)
+        .await
+        .map_err(|_| warp::reject::custom(ApiError::Internal))?
+        .ok_or_else(|| warp::reject::custom(ApiError::Unauthorized))?;
+
+    let user = state
+        .users
+        .find_one(doc! { "email": &stored.email }, None)
+        .await
+        .map_err(|_| warp::reject::custom(ApiError::Internal))?
+        .ok_or_else(|| warp::reject::custom(ApiError::Unauthorized))?;
+
+    let access_token = create_access_token(&user.email, &user.role, &state)?;
+    let refresh_token = create_refresh_token(&user.email, &state).await?;
+
+    Ok(warp::reply::json(&TokenResponse { access_token, refresh_token }))
+}
+
+// Revoke a refresh token so it can no longer be used to mint access tokens
+async fn logout(req: LogoutRequest, state: AppState) -> Result<impl warp::Reply, Rejection> {
+    let stored = find_valid_refresh_token(&req.refresh_token, &state).await?;
+
+    state
+        .refresh_tokens
+        .update_one(doc! { "jti": &stored.jti }, doc! { "$set": { "revoked": true } }, None)
+        .await
+        .map_err(|_| warp::reject::custom(ApiError::Internal))?;
+
+    Ok(warp::reply::json(&StatusResponse { status: "ok" }))
+}
+
+// Extracts and validates the `Authorization: Bearer <token>` header, yielding
+// the decoded `Claims` on success and rejecting with `ApiError::Unauthorized` otherwise.
+fn with_auth(jwt_secret: Vec<u8>) -> impl Filter<Extract = (Claims,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization").and_then(move |header: Option<String>| {
+        let jwt_secret = jwt_secret.clone();
+        async move {
+            let header = header.ok_or_else(|| warp::reject::custom(ApiError::Unauthorized))?;
+            let token = header
+                .strip_prefix("Bearer ")
+                .ok_or_else(|| warp::reject::custom(ApiError::Unauthorized))?;
+
+            decode::<Claims>(token, &DecodingKey::from_secret(&jwt_secret), &Validation::default())
+                .map(|data| data.claims)
+                .map_err(|_| warp::reject::custom(ApiError::Unauthorized))
+        }
+    })
+}
+
+// Like `with_auth`, but additionally rejects with `ApiError::Forbidden` unless
+// the token's `role` claim matches `required_role`, enabling tiered
+// (e.g. admin-only vs. general) endpoints from a single token.
+fn with_scope(
+    jwt_secret: Vec<u8>,
+    required_role: &'static str,
+) -> impl Filter<Extract = (Claims,), Error = Rejection> + Clone {
+    with_auth(jwt_secret).and_then(move |claims: Claims| async move {
+        if claims.role == required_role {
+            Ok(claims)
+        } else {
+            Err(warp::reject::custom(ApiError::Forbidden))
+        }
+    })
+}
+
+// Return the authenticated user's email for a valid bearer token
+async fn whoami(claims: Claims) -> Result<impl warp::Reply, Infallible> {
+    Ok(warp::reply::json(&WhoamiResponse { email: claims.email }))
+}
+
+// Minimal example of an admin-only endpoint gated by `with_scope`
+async fn admin_ping(_claims: Claims) -> Result<impl warp::Reply, Infallible> {
+    Ok(warp::reply::json(&StatusResponse { status: "ok" }))
 }
 
 #[tokio::main]
 async fn main() {
+    // Load configuration from the environment / .env
+    let config = Config::from_env();
+
     // Connect to MongoDB
-    let collection = database::get_collection().await;
+    let database = database::connect(&config.mongodb_uri, &config.database_name).await;
+    let users = database::get_collection(&database, &config.collection_name).await;
+    let refresh_tokens = database::get_refresh_token_collection(&database, &config.refresh_collection_name).await;
+
+    let state = AppState {
+        users,
+        refresh_tokens,
+        jwt_secret: config.jwt_secret.clone(),
+        access_token_ttl_minutes: config.access_token_ttl_minutes,
+        refresh_token_ttl_days: config.refresh_token_ttl_days,
+    };
 
     // Define the register route
     let register = warp::path("register")
         .and(warp::post())
         .and(warp::body::json())
-        .and_then(move |user: RegisterRequest| {
-            let collection = collection.clone();
-            async move { register_user(user, collection).await }
-        });
+        .and(with_state(state.clone()))
+        .and_then(register_user);
+
+    // Define the login route
+    let login = warp::path("login")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_state(state.clone()))
+        .and_then(login_user);
+
+    // Define the refresh route
+    let refresh_route = warp::path("refresh")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_state(state.clone()))
+        .and_then(refresh);
+
+    // Define the logout route
+    let logout_route = warp::path("logout")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_state(state.clone()))
+        .and_then(logout);
+
+    // Define the protected whoami route
+    let whoami_route = warp::path("whoami")
+        .and(warp::get())
+        .and(with_auth(config.jwt_secret.clone()))
+        .and_then(whoami);
+
+    // Example admin-only route, gated on the "admin" role claim
+    let admin_ping_route = warp::path!("admin" / "ping")
+        .and(warp::get())
+        .and(with_scope(config.jwt_secret.clone(), "admin"))
+        .and_then(admin_ping);
+
+    // Build the configurable CORS layer so browser clients on allowed
+    // origins can call the API (and have their OPTIONS preflight answered)
+    let cors = {
+        let mut cors = warp::cors()
+            .allow_methods(config.cors_allowed_methods.iter().map(String::as_str))
+            .allow_headers(config.cors_allowed_headers.iter().map(String::as_str));
+        for origin in &config.cors_allowed_origins {
+            cors = cors.allow_origin(origin.as_str());
+        }
+        cors.build()
+    };
+
+    let routes = register
+        .or(login)
+        .or(refresh_route)
+        .or(logout_route)
+        .or(whoami_route)
+        .or(admin_ping_route)
+        .recover(error::handle_rejection)
+        .with(cors);
 
     // Start the server
-    warp::serve(register).run(([127, 0, 0, 1], 3030)).await;
-}
\ No newline at end of file
+    warp::serve(routes).run(([127, 0, 0, 1], config.server_port)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_token(secret: &str, revoked: bool, expires_at: i64) -> models::RefreshToken {
+        models::RefreshToken {
+            jti: "test-jti".to_string(),
+            email: "user@example.com".to_string(),
+            secret_hash: hash(secret, DEFAULT_COST).unwrap(),
+            expires_at,
+            revoked,
+        }
+    }
+
+    #[test]
+    fn valid_token_is_usable() {
+        let token = sample_token("correct-secret", false, Utc::now().timestamp() + 60);
+        assert!(is_refresh_token_usable(&token, "correct-secret", Utc::now().timestamp()));
+    }
+
+    // (b) replaying a token after `refresh` has rotated it (flipping `revoked`)
+    // must be rejected - this is the reuse-detection signal.
+    #[test]
+    fn rotated_token_is_rejected_as_reuse() {
+        let token = sample_token("correct-secret", true, Utc::now().timestamp() + 60);
+        assert!(!is_refresh_token_usable(&token, "correct-secret", Utc::now().timestamp()));
+    }
+
+    // (c) a correct `jti` with the wrong secret must be rejected without
+    // ever touching `revoked` - `refresh` only issues its conditional
+    // `find_one_and_update` after this check passes.
+    #[test]
+    fn wrong_secret_is_rejected_without_mutating_state() {
+        let token = sample_token("correct-secret", false, Utc::now().timestamp() + 60);
+        assert!(!is_refresh_token_usable(&token, "wrong-secret", Utc::now().timestamp()));
+        assert!(!token.revoked);
+    }
+
+    #[test]
+    fn expired_token_is_not_usable() {
+        let token = sample_token("correct-secret", false, Utc::now().timestamp() - 1);
+        assert!(!is_refresh_token_usable(&token, "correct-secret", Utc::now().timestamp()));
+    }
+}
@@ -0,0 +1,27 @@
+// src/models.rs
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct User {
+    pub email: String,
+    pub password: String,
+    #[serde(default = "default_role")]
+    pub role: String,
+}
+
+/// Role assigned to documents written before the `role` field existed.
+fn default_role() -> String {
+    "user".to_string()
+}
+
+/// A persisted refresh token. `jti` is the public, non-secret lookup key;
+/// `secret_hash` is the bcrypt hash of the secret half of the token the
+/// client holds, so a leaked database never exposes usable tokens.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RefreshToken {
+    pub jti: String,
+    pub email: String,
+    pub secret_hash: String,
+    pub expires_at: i64,
+    pub revoked: bool,
+}